@@ -1,23 +1,310 @@
 use chrono::prelude::*;
-use serde::Deserialize;
-use std::convert::From;
-use std::io::Read;
-use std::time::Duration;
+use dimensioned::si::{Meter, Second, M, S};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::{From, TryFrom};
+use std::fmt;
+use std::io::{Read, Write};
 use thiserror::Error;
+use xml::writer::{EventWriter, XmlEvent};
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("XML Parse error: {0}")]
     Parse(#[from] serde_xml_rs::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("XML write error: {0}")]
+    Write(#[from] xml::writer::Error),
+
+    #[error("invalid distance: {0}")]
+    InvalidDistance(String),
+
+    #[error("invalid duration: {0}")]
+    InvalidDuration(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatOption {
+    Abbreviated,
+    Full,
+}
+
+const METERS_PER_MILE: f64 = 1609.344;
+const ALTITUDE_HYSTERESIS_METERS: f64 = 3.0;
+
+fn format_clock(total_secs: u64) -> String {
+    let hours = total_secs / 3600;
+    let mins = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, mins, secs)
+    } else {
+        format!("{}:{:02}", mins, secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distance(Meter<f64>);
+
+impl Distance {
+    pub fn from_meters(meters: f64) -> Self {
+        Self(meters * M)
+    }
+
+    pub fn meters(&self) -> f64 {
+        *(self.0 / M)
+    }
+
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+
+        let meters = if let Some(value) = s.strip_suffix("km") {
+            parse_f64(value, s)? * 1000.0
+        } else if let Some(value) = s.strip_suffix("mi") {
+            parse_f64(value, s)? * METERS_PER_MILE
+        } else if let Some(value) = s.strip_suffix('m') {
+            parse_f64(value, s)?
+        } else {
+            parse_f64(s, s)?
+        };
+
+        Ok(Self::from_meters(meters))
+    }
+
+    pub fn format(&self, units: UnitSystem, option: FormatOption) -> String {
+        match units {
+            UnitSystem::Metric => {
+                let km = self.meters() / 1000.0;
+                match option {
+                    FormatOption::Abbreviated => format!("{:.2} km", km),
+                    FormatOption::Full => format!("{:.2} kilometers", km),
+                }
+            }
+            UnitSystem::Imperial => {
+                let mi = self.meters() / METERS_PER_MILE;
+                match option {
+                    FormatOption::Abbreviated => format!("{:.2} mi", mi),
+                    FormatOption::Full => format!("{:.2} miles", mi),
+                }
+            }
+        }
+    }
+}
+
+fn parse_f64(value: &str, original: &str) -> Result<f64, Error> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::InvalidDistance(original.to_string()))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Duration(Second<f64>);
+
+impl Duration {
+    pub fn from_secs_f64(secs: f64) -> Self {
+        Self(secs * S)
+    }
+
+    pub fn seconds(&self) -> f64 {
+        *(self.0 / S)
+    }
+
+    pub fn parse(original: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidDuration(original.to_string());
+        let parts: Vec<&str> = original.trim().split(':').collect();
+
+        let secs = match parts.as_slice() {
+            [h, m, s] => {
+                let h: f64 = h.parse().map_err(|_| invalid())?;
+                let m: f64 = m.parse().map_err(|_| invalid())?;
+                let s: f64 = s.parse().map_err(|_| invalid())?;
+                h * 3600.0 + m * 60.0 + s
+            }
+            [m, s] => {
+                let m: f64 = m.parse().map_err(|_| invalid())?;
+                let s: f64 = s.parse().map_err(|_| invalid())?;
+                m * 60.0 + s
+            }
+            [s] => s.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self::from_secs_f64(secs))
+    }
+
+    pub fn format(&self, option: FormatOption) -> String {
+        let total_secs = self.seconds().round() as u64;
+
+        match option {
+            FormatOption::Abbreviated => format_clock(total_secs),
+            FormatOption::Full => {
+                let hours = total_secs / 3600;
+                let mins = (total_secs % 3600) / 60;
+                let secs = total_secs % 60;
+
+                if hours > 0 {
+                    format!("{} hours {} minutes {} seconds", hours, mins, secs)
+                } else {
+                    format!("{} minutes {} seconds", mins, secs)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pace {
+    seconds_per_meter: f64,
+}
+
+impl Pace {
+    pub fn new(duration: Duration, distance: Distance) -> Self {
+        let meters = distance.meters();
+        let seconds_per_meter = if meters > 0.0 {
+            duration.seconds() / meters
+        } else {
+            0.0
+        };
+
+        Self { seconds_per_meter }
+    }
+
+    pub fn format(&self, units: UnitSystem, option: FormatOption) -> String {
+        let unit_meters = match units {
+            UnitSystem::Metric => 1000.0,
+            UnitSystem::Imperial => METERS_PER_MILE,
+        };
+        let total_secs = (self.seconds_per_meter * unit_meters).round() as u64;
+        let time = format_clock(total_secs);
+
+        let unit = match (units, option) {
+            (UnitSystem::Metric, FormatOption::Abbreviated) => "min/km",
+            (UnitSystem::Metric, FormatOption::Full) => "minutes per kilometer",
+            (UnitSystem::Imperial, FormatOption::Abbreviated) => "min/mi",
+            (UnitSystem::Imperial, FormatOption::Full) => "minutes per mile",
+        };
+
+        format!("{} {}", time, unit)
+    }
+}
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Great-circle distance between two [`Position`]s via the haversine
+/// formula, used to derive splits when trackpoints carry no `DistanceMeters`.
+fn haversine_distance(a: &Position, b: &Position) -> f64 {
+    let lat1 = a.lat.to_radians();
+    let lat2 = b.lat.to_radians();
+    let dlat = (b.lat - a.lat).to_radians();
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
+
+fn average_heart_rate(values: &[i32]) -> i32 {
+    if values.is_empty() {
+        0
+    } else {
+        values.iter().sum::<i32>() / values.len() as i32
+    }
+}
+
+/// One even split of an [`Activity`], e.g. one kilometer.
+#[derive(Debug, Clone, Copy)]
+pub struct Split {
+    pub distance: Distance,
+    pub duration: Duration,
+    pub pace: Pace,
+    pub heart_rate: i32,
+}
+
+/// A timestamp that retains the athlete's local timezone instead of forcing
+/// everything to UTC, serialized as `"<RFC3339> <IANA Timezone Name>"`
+/// (e.g. `"2024-02-19T14:24:52-05:00 America/New_York"`).
+#[derive(Debug, Clone, Copy)]
+pub struct DateTimeTz(pub DateTime<chrono_tz::Tz>);
+
+impl DateTimeTz {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.rsplit_once(' ') {
+            Some((rfc3339, tz_name)) => {
+                let tz: chrono_tz::Tz = tz_name
+                    .parse()
+                    .map_err(|_| format!("unknown timezone: {}", tz_name))?;
+                let dt = DateTime::parse_from_rfc3339(rfc3339)
+                    .map_err(|e| e.to_string())?
+                    .with_timezone(&tz);
+                Ok(Self(dt))
+            }
+            None => {
+                let dt = DateTime::parse_from_rfc3339(s)
+                    .map_err(|e| e.to_string())?
+                    .with_timezone(&chrono_tz::UTC);
+                Ok(Self(dt))
+            }
+        }
+    }
+
+    fn tcx_string(&self) -> String {
+        format!("{} {}", self.0.to_rfc3339(), self.0.timezone().name())
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTimeTz {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DateTimeTzVisitor;
+
+        impl<'de> Visitor<'de> for DateTimeTzVisitor {
+            type Value = DateTimeTz;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC3339 timestamp, optionally followed by an IANA timezone name")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                DateTimeTz::parse(v).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(DateTimeTzVisitor)
+    }
+}
+
+impl Serialize for DateTimeTz {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.tcx_string())
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct HeartRate {
     #[serde(rename = "Value")]
     pub value: i32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Position {
     #[serde(rename = "LatitudeDegrees")]
     pub lat: f64,
@@ -26,20 +313,35 @@ pub struct Position {
     pub lon: f64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum SensorState {
     Present,
     Absent,
 }
 
-#[derive(Deserialize, Debug)]
+impl SensorState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SensorState::Present => "Present",
+            SensorState::Absent => "Absent",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Sample {
     #[serde(rename = "Time")]
-    pub time: chrono::DateTime<Utc>,
+    pub time: DateTimeTz,
 
     #[serde(rename = "Position")]
     pub position: Option<Position>,
 
+    #[serde(rename = "AltitudeMeters")]
+    pub altitude: Option<f64>,
+
+    #[serde(rename = "DistanceMeters")]
+    pub distance: Option<f64>,
+
     #[serde(rename = "HeartRateBpm")]
     pub heart_rate: HeartRate,
 
@@ -47,13 +349,13 @@ pub struct Sample {
     pub sensor_state: SensorState,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Track {
     #[serde(rename = "Trackpoint")]
     pub samples: Vec<Sample>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Lap {
     #[serde(rename = "TotalTimeSeconds")]
     pub time: f64,
@@ -71,32 +373,99 @@ pub struct Lap {
     pub cadence: i32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sport {
     Running,
-    Biking,
+    Cycling,
+    Rowing,
+    Swimming,
+    Walking,
     Other,
 }
 
-#[derive(Deserialize, Debug)]
+// Kept as `TryFrom` (with an infallible error) rather than `From` so the
+// signature leaves room for stricter parsing later without a breaking change.
+#[allow(clippy::infallible_try_from)]
+impl TryFrom<&str> for Sport {
+    type Error = std::convert::Infallible;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Ok(match s {
+            "Running" => Sport::Running,
+            "Biking" | "Cycling" => Sport::Cycling,
+            "Rowing" => Sport::Rowing,
+            "Swimming" => Sport::Swimming,
+            "Walking" => Sport::Walking,
+            _ => Sport::Other,
+        })
+    }
+}
+
+impl std::str::FromStr for Sport {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sport::try_from(s)
+    }
+}
+
+impl Sport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sport::Running => "Running",
+            Sport::Cycling => "Cycling",
+            Sport::Rowing => "Rowing",
+            Sport::Swimming => "Swimming",
+            Sport::Walking => "Walking",
+            Sport::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for Sport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Sport {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(Sport::try_from(s.as_str()).unwrap())
+    }
+}
+
+impl Serialize for Sport {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Activity {
     #[serde(rename = "Sport")]
     pub sport: Sport,
 
     #[serde(rename = "Id")]
-    pub id: chrono::DateTime<Utc>,
+    pub id: DateTimeTz,
 
     #[serde(rename = "Lap")]
     pub laps: Vec<Lap>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Activities {
     #[serde(rename = "Activity")]
     pub activity: Activity,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename = "TrainingCenterDatabase")]
 pub struct Database {
     #[serde(rename = "Activities")]
@@ -113,11 +482,131 @@ impl Database {
     pub fn new<R: Read>(reader: R) -> Result<Self, Error> {
         Ok(serde_xml_rs::from_reader(reader)?)
     }
+
+    /// Writes this database back out as TCX XML.
+    ///
+    /// `serde_xml_rs` can only derive a `Serializer` for scalar leaves; fed a
+    /// nested struct with `Vec` fields (e.g. `Track`'s trackpoints) it mixes up
+    /// the enclosing element name with the variant being written and errors
+    /// out entirely once a `Lap` has a non-empty `Track`. So this walks the
+    /// tree by hand with `xml-rs`'s `EventWriter` instead.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut writer = EventWriter::new(writer);
+        writer.write(XmlEvent::start_element("TrainingCenterDatabase"))?;
+        for activities in &self.activities {
+            activities.write_xml(&mut writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+fn write_text_element<W: Write>(
+    writer: &mut EventWriter<W>,
+    name: &str,
+    text: &str,
+) -> Result<(), Error> {
+    writer.write(XmlEvent::start_element(name))?;
+    writer.write(XmlEvent::characters(text))?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+impl Activities {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Activities"))?;
+        self.activity.write_xml(writer)?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Activity {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Activity"))?;
+        write_text_element(writer, "Sport", self.sport.as_str())?;
+        write_text_element(writer, "Id", &self.id.tcx_string())?;
+        for lap in &self.laps {
+            lap.write_xml(writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Lap {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Lap"))?;
+        write_text_element(writer, "TotalTimeSeconds", &self.time.to_string())?;
+        write_text_element(writer, "DistanceMeters", &self.distance.to_string())?;
+        self.track.write_xml(writer)?;
+        write_text_element(writer, "Calories", &self.calories.to_string())?;
+        write_text_element(writer, "Cadence", &self.cadence.to_string())?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Track {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Track"))?;
+        for sample in &self.samples {
+            sample.write_xml(writer)?;
+        }
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Sample {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Trackpoint"))?;
+        write_text_element(writer, "Time", &self.time.tcx_string())?;
+        if let Some(position) = &self.position {
+            position.write_xml(writer)?;
+        }
+        if let Some(altitude) = self.altitude {
+            write_text_element(writer, "AltitudeMeters", &altitude.to_string())?;
+        }
+        if let Some(distance) = self.distance {
+            write_text_element(writer, "DistanceMeters", &distance.to_string())?;
+        }
+        writer.write(XmlEvent::start_element("HeartRateBpm"))?;
+        self.heart_rate.write_xml(writer)?;
+        writer.write(XmlEvent::end_element())?;
+        write_text_element(writer, "SensorState", self.sensor_state.as_str())?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl Position {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        writer.write(XmlEvent::start_element("Position"))?;
+        write_text_element(writer, "LatitudeDegrees", &self.lat.to_string())?;
+        write_text_element(writer, "LongitudeDegrees", &self.lon.to_string())?;
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+}
+
+impl HeartRate {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<(), Error> {
+        write_text_element(writer, "Value", &self.value.to_string())
+    }
 }
 
 impl Track {
     pub fn heart_rate(&self) -> i32 {
-        if self.samples.len() == 0 {
+        if self.samples.is_empty() {
             return 0;
         }
 
@@ -126,17 +615,20 @@ impl Track {
 }
 
 impl Activity {
-    pub fn distance(&self) -> f64 {
-        self.laps.iter().map(|l| l.distance).sum()
+    pub fn distance(&self) -> Distance {
+        Distance::from_meters(self.laps.iter().map(|l| l.distance).sum())
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.laps.iter().map(|l| l.time).sum())
     }
 
-    pub fn duration(&self) -> chrono::Duration {
-        let secs = self.laps.iter().map(|l| l.time as u64).sum();
-        chrono::Duration::from_std(Duration::from_secs(secs)).unwrap()
+    pub fn pace(&self) -> Pace {
+        Pace::new(self.duration(), self.distance())
     }
 
     pub fn heart_rate(&self) -> i32 {
-        if self.laps.len() == 0 {
+        if self.laps.is_empty() {
             return 0;
         }
 
@@ -150,4 +642,462 @@ impl Activity {
     pub fn cadence(&self) -> i32 {
         self.laps.iter().map(|l| l.cadence).sum::<i32>() / self.laps.len() as i32
     }
+
+    pub fn ascent(&self) -> f64 {
+        self.elevation_gain_loss().0
+    }
+
+    pub fn descent(&self) -> f64 {
+        self.elevation_gain_loss().1
+    }
+
+    fn elevation_gain_loss(&self) -> (f64, f64) {
+        let mut ascent = 0.0;
+        let mut descent = 0.0;
+        let mut reference: Option<f64> = None;
+
+        for sample in self.laps.iter().flat_map(|l| l.track.samples.iter()) {
+            let altitude = match sample.altitude {
+                Some(altitude) => altitude,
+                None => continue,
+            };
+
+            let reference_altitude = match reference {
+                Some(reference_altitude) => reference_altitude,
+                None => {
+                    reference = Some(altitude);
+                    continue;
+                }
+            };
+
+            let delta = altitude - reference_altitude;
+            if delta.abs() >= ALTITUDE_HYSTERESIS_METERS {
+                if delta > 0.0 {
+                    ascent += delta;
+                } else {
+                    descent -= delta;
+                }
+                reference = Some(altitude);
+            }
+        }
+
+        (ascent, descent)
+    }
+
+    /// Derives even splits (e.g. 1 km) by scanning the ordered trackpoints
+    /// across all laps, interpolating the crossing time at each split
+    /// boundary from the cumulative distance. A final, partial split covers
+    /// the remainder.
+    pub fn splits(&self, split_len: Distance) -> Vec<Split> {
+        let split_meters = split_len.meters();
+        if split_meters <= 0.0 {
+            return Vec::new();
+        }
+
+        let samples: Vec<&Sample> = self
+            .laps
+            .iter()
+            .flat_map(|l| l.track.samples.iter())
+            .collect();
+
+        let start_time = match samples.first() {
+            Some(sample) => sample.time.0,
+            None => return Vec::new(),
+        };
+
+        let elapsed_secs =
+            |sample: &Sample| (sample.time.0 - start_time).num_milliseconds() as f64 / 1000.0;
+
+        let mut splits = Vec::new();
+        let mut cumulative_distance = 0.0;
+        let mut next_boundary = split_meters;
+        let mut split_start_distance = 0.0;
+        let mut split_start_elapsed = 0.0;
+        let mut heart_rates = vec![samples[0].heart_rate.value];
+
+        for pair in samples.windows(2) {
+            let (prev, sample) = (pair[0], pair[1]);
+
+            let delta = match (prev.distance, sample.distance) {
+                (Some(p), Some(s)) => s - p,
+                _ => match (&prev.position, &sample.position) {
+                    (Some(p), Some(s)) => haversine_distance(p, s),
+                    _ => 0.0,
+                },
+            };
+
+            let prev_elapsed = elapsed_secs(prev);
+            let elapsed = elapsed_secs(sample);
+            let prev_cumulative = cumulative_distance;
+            cumulative_distance += delta;
+            heart_rates.push(sample.heart_rate.value);
+
+            while cumulative_distance >= next_boundary {
+                let fraction = if delta > 0.0 {
+                    (next_boundary - prev_cumulative) / delta
+                } else {
+                    0.0
+                };
+                let crossing_elapsed = prev_elapsed + (elapsed - prev_elapsed) * fraction;
+
+                let distance = Distance::from_meters(next_boundary - split_start_distance);
+                let duration = Duration::from_secs_f64(crossing_elapsed - split_start_elapsed);
+
+                splits.push(Split {
+                    distance,
+                    duration,
+                    pace: Pace::new(duration, distance),
+                    heart_rate: average_heart_rate(&heart_rates),
+                });
+
+                split_start_distance = next_boundary;
+                split_start_elapsed = crossing_elapsed;
+                next_boundary += split_meters;
+
+                // Seed the next split with the current sample's heart rate
+                // rather than clearing outright: a single prev/sample gap can
+                // cross several boundaries at once (GPS dropout, a paused and
+                // resumed device), and an empty vec would make
+                // `average_heart_rate` report 0 bpm for those splits.
+                heart_rates = vec![sample.heart_rate.value];
+            }
+        }
+
+        if cumulative_distance > split_start_distance {
+            let last_elapsed = elapsed_secs(samples[samples.len() - 1]);
+            let distance = Distance::from_meters(cumulative_distance - split_start_distance);
+            let duration = Duration::from_secs_f64(last_elapsed - split_start_elapsed);
+
+            splits.push(Split {
+                distance,
+                duration,
+                pace: Pace::new(duration, distance),
+                heart_rate: average_heart_rate(&heart_rates),
+            });
+        }
+
+        splits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_at(elapsed_secs: i64, altitude: Option<f64>, distance: Option<f64>, hr: i32) -> Sample {
+        let base = DateTimeTz::parse("2024-01-01T00:00:00Z").unwrap();
+        Sample {
+            time: DateTimeTz(base.0 + chrono::Duration::seconds(elapsed_secs)),
+            position: None,
+            altitude,
+            distance,
+            heart_rate: HeartRate { value: hr },
+            sensor_state: SensorState::Present,
+        }
+    }
+
+    fn activity_with_samples(samples: Vec<Sample>) -> Activity {
+        Activity {
+            sport: Sport::Running,
+            id: DateTimeTz::parse("2024-01-01T00:00:00Z").unwrap(),
+            laps: vec![Lap {
+                time: 0.0,
+                distance: 0.0,
+                track: Track { samples },
+                calories: 0,
+                cadence: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn splits_carry_heart_rate_across_multiple_boundaries_in_one_gap() {
+        let activity = activity_with_samples(vec![
+            sample_at(0, None, Some(0.0), 140),
+            sample_at(250, None, Some(2500.0), 160),
+        ]);
+
+        let splits = activity.splits(Distance::from_meters(1000.0));
+
+        assert_eq!(splits.len(), 3);
+        assert_eq!(splits[0].heart_rate, 150);
+        // Split 1 is entirely inside the single prev/sample gap that already
+        // produced split 0 in the same `while` pass - it must not fall back
+        // to the empty-vec 0 bpm.
+        assert_eq!(splits[1].heart_rate, 160);
+        assert_eq!(splits[2].heart_rate, 160);
+    }
+
+    #[test]
+    fn splits_using_distance_meters_over_even_kilometers() {
+        let activity = activity_with_samples(vec![
+            sample_at(0, None, Some(0.0), 140),
+            sample_at(300, None, Some(1000.0), 150),
+            sample_at(600, None, Some(2000.0), 160),
+        ]);
+
+        let splits = activity.splits(Distance::from_meters(1000.0));
+
+        assert_eq!(splits.len(), 2);
+
+        assert!((splits[0].distance.meters() - 1000.0).abs() < 1e-9);
+        assert_eq!(splits[0].duration.seconds(), 300.0);
+        assert_eq!(splits[0].heart_rate, 145);
+        assert_eq!(
+            splits[0].pace.format(UnitSystem::Metric, FormatOption::Abbreviated),
+            "5:00 min/km"
+        );
+
+        assert!((splits[1].distance.meters() - 1000.0).abs() < 1e-9);
+        assert_eq!(splits[1].duration.seconds(), 300.0);
+        assert_eq!(splits[1].heart_rate, 155);
+    }
+
+    #[test]
+    fn splits_fall_back_to_haversine_distance_without_distance_meters() {
+        let start = Position {
+            lat: 52.5,
+            lon: 13.4,
+        };
+        let end = Position {
+            lat: 52.5135,
+            lon: 13.4,
+        };
+        let total_meters = haversine_distance(&start, &end);
+
+        let activity = activity_with_samples(vec![
+            Sample {
+                position: Some(start),
+                ..sample_at(0, None, None, 140)
+            },
+            Sample {
+                position: Some(end),
+                ..sample_at(300, None, None, 160)
+            },
+        ]);
+
+        let splits = activity.splits(Distance::from_meters(1000.0));
+
+        assert_eq!(splits.len(), 2);
+
+        assert!((splits[0].distance.meters() - 1000.0).abs() < 1e-6);
+        let remainder = total_meters - 1000.0;
+        assert!((splits[1].distance.meters() - remainder).abs() < 1e-6);
+
+        let expected_crossing = 300.0 * (1000.0 / total_meters);
+        assert!((splits[0].duration.seconds() - expected_crossing).abs() < 1e-6);
+        assert!((splits[1].duration.seconds() - (300.0 - expected_crossing)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn elevation_ignores_deltas_below_hysteresis_threshold() {
+        let activity = activity_with_samples(vec![
+            sample_at(0, Some(100.0), None, 0),
+            sample_at(1, Some(102.9), None, 0),
+        ]);
+
+        assert_eq!(activity.ascent(), 0.0);
+        assert_eq!(activity.descent(), 0.0);
+    }
+
+    #[test]
+    fn elevation_commits_deltas_above_hysteresis_threshold() {
+        let activity = activity_with_samples(vec![
+            sample_at(0, Some(100.0), None, 0),
+            sample_at(1, Some(103.1), None, 0),
+            sample_at(2, Some(101.2), None, 0),
+            sample_at(3, Some(99.0), None, 0),
+        ]);
+
+        assert!((activity.ascent() - 3.1).abs() < 1e-9);
+        assert!((activity.descent() - 4.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn distance_parses_km_mi_and_bare_meters() {
+        assert_eq!(Distance::parse("5km").unwrap().meters(), 5000.0);
+        assert_eq!(Distance::parse("1mi").unwrap().meters(), METERS_PER_MILE);
+        assert_eq!(Distance::parse("5000m").unwrap().meters(), 5000.0);
+        assert_eq!(Distance::parse("42").unwrap().meters(), 42.0);
+    }
+
+    #[test]
+    fn distance_rejects_garbage() {
+        assert!(Distance::parse("not a distance").is_err());
+    }
+
+    #[test]
+    fn sport_aliases_biking_to_cycling() {
+        assert_eq!(Sport::try_from("Biking").unwrap(), Sport::Cycling);
+        assert_eq!(Sport::try_from("Cycling").unwrap(), Sport::Cycling);
+    }
+
+    #[test]
+    fn sport_parses_known_variants() {
+        assert_eq!(Sport::try_from("Running").unwrap(), Sport::Running);
+        assert_eq!(Sport::try_from("Rowing").unwrap(), Sport::Rowing);
+        assert_eq!(Sport::try_from("Swimming").unwrap(), Sport::Swimming);
+        assert_eq!(Sport::try_from("Walking").unwrap(), Sport::Walking);
+    }
+
+    #[test]
+    fn sport_falls_back_to_other_for_unknown_strings() {
+        assert_eq!(Sport::try_from("Kayaking").unwrap(), Sport::Other);
+        assert_eq!(Sport::try_from("").unwrap(), Sport::Other);
+    }
+
+    #[test]
+    fn sport_as_str_and_display_round_trip_each_variant() {
+        for sport in [
+            Sport::Running,
+            Sport::Cycling,
+            Sport::Rowing,
+            Sport::Swimming,
+            Sport::Walking,
+            Sport::Other,
+        ] {
+            assert_eq!(Sport::try_from(sport.as_str()).unwrap(), sport);
+            assert_eq!(sport.to_string(), sport.as_str());
+        }
+    }
+
+    #[test]
+    fn duration_parses_hms_ms_and_bare_seconds() {
+        assert_eq!(Duration::parse("1:02:03").unwrap().seconds(), 3723.0);
+        assert_eq!(Duration::parse("4:05").unwrap().seconds(), 245.0);
+        assert_eq!(Duration::parse("30").unwrap().seconds(), 30.0);
+    }
+
+    #[test]
+    fn duration_format_zero_pads_minutes_and_seconds() {
+        let duration = Duration::from_secs_f64(245.0);
+        assert_eq!(duration.format(FormatOption::Abbreviated), "4:05");
+    }
+
+    #[test]
+    fn pace_format_zero_pads_seconds() {
+        let pace = Pace::new(Duration::from_secs_f64(245.0), Distance::from_meters(1000.0));
+        assert_eq!(
+            pace.format(UnitSystem::Metric, FormatOption::Abbreviated),
+            "4:05 min/km"
+        );
+    }
+
+    #[test]
+    fn duration_rejects_garbage() {
+        assert!(Duration::parse("not a duration").is_err());
+    }
+
+    #[test]
+    fn datetimetz_parses_with_timezone_suffix() {
+        let dt = DateTimeTz::parse("2024-02-19T14:24:52-05:00 America/New_York").unwrap();
+        assert_eq!(dt.0.timezone().name(), "America/New_York");
+        assert_eq!(dt.0.hour(), 14);
+    }
+
+    #[test]
+    fn datetimetz_falls_back_to_utc_without_timezone_suffix() {
+        let dt = DateTimeTz::parse("2024-02-19T14:24:52Z").unwrap();
+        assert_eq!(dt.0.timezone().name(), "UTC");
+    }
+
+    #[test]
+    fn datetimetz_rejects_garbage() {
+        assert!(DateTimeTz::parse("not a timestamp").is_err());
+    }
+
+    fn sample_database() -> Database {
+        let mut activity = activity_with_samples(vec![
+            Sample {
+                position: Some(Position {
+                    lat: 52.5,
+                    lon: 13.4,
+                }),
+                ..sample_at(0, Some(34.0), Some(0.0), 140)
+            },
+            Sample {
+                position: Some(Position {
+                    lat: 52.501,
+                    lon: 13.401,
+                }),
+                ..sample_at(300, Some(37.0), Some(1000.0), 150)
+            },
+        ]);
+        activity.laps[0].time = 300.0;
+        activity.laps[0].distance = 1000.0;
+        activity.laps[0].calories = 60;
+        activity.laps[0].cadence = 80;
+
+        Database {
+            activities: vec![Activities { activity }],
+        }
+    }
+
+    #[test]
+    fn database_write_round_trips_through_new() {
+        let database = sample_database();
+
+        let mut xml = Vec::new();
+        database.write(&mut xml).unwrap();
+
+        let round_tripped = Database::new(xml.as_slice()).unwrap();
+
+        let activity = &round_tripped.activities[0].activity;
+        assert_eq!(activity.sport, Sport::Running);
+        assert_eq!(activity.laps.len(), 1);
+
+        let lap = &activity.laps[0];
+        assert_eq!(lap.time, 300.0);
+        assert_eq!(lap.distance, 1000.0);
+        assert_eq!(lap.calories, 60);
+        assert_eq!(lap.cadence, 80);
+        assert_eq!(lap.track.samples.len(), 2);
+
+        let first = &lap.track.samples[0];
+        assert_eq!(first.altitude, Some(34.0));
+        assert_eq!(first.distance, Some(0.0));
+        assert_eq!(first.heart_rate.value, 140);
+        assert!(matches!(first.sensor_state, SensorState::Present));
+        let position = first.position.as_ref().unwrap();
+        assert_eq!(position.lat, 52.5);
+        assert_eq!(position.lon, 13.4);
+
+        let second = &lap.track.samples[1];
+        assert_eq!(second.altitude, Some(37.0));
+        assert_eq!(second.distance, Some(1000.0));
+        assert_eq!(second.heart_rate.value, 150);
+    }
+
+    #[test]
+    fn database_to_json_round_trips_through_from_json() {
+        let database = sample_database();
+
+        let json = database.to_json().unwrap();
+        let round_tripped = Database::from_json(&json).unwrap();
+
+        let activity = &round_tripped.activities[0].activity;
+        assert_eq!(activity.sport, Sport::Running);
+        assert_eq!(activity.id.0, database.activities[0].activity.id.0);
+
+        let lap = &activity.laps[0];
+        assert_eq!(lap.time, 300.0);
+        assert_eq!(lap.distance, 1000.0);
+        assert_eq!(lap.calories, 60);
+        assert_eq!(lap.cadence, 80);
+        assert_eq!(lap.track.samples.len(), 2);
+
+        let first = &lap.track.samples[0];
+        assert_eq!(first.altitude, Some(34.0));
+        assert_eq!(first.distance, Some(0.0));
+        assert_eq!(first.heart_rate.value, 140);
+        assert!(matches!(first.sensor_state, SensorState::Present));
+        let position = first.position.as_ref().unwrap();
+        assert_eq!(position.lat, 52.5);
+        assert_eq!(position.lon, 13.4);
+
+        let second = &lap.track.samples[1];
+        assert_eq!(second.altitude, Some(37.0));
+        assert_eq!(second.distance, Some(1000.0));
+        assert_eq!(second.heart_rate.value, 150);
+    }
 }